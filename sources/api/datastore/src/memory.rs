@@ -2,20 +2,26 @@
 //!
 //! Mimics some of the decisions made for FilesystemDataStore, e.g. metadata being committed
 //! immediately.
+//!
+//! The underlying maps are reference-counted so that a datastore can be cheaply forked, for
+//! example to branch test fixtures.  A fork shares its maps with the original until either side
+//! writes to one, at which point that whole map is copied; for example, committing a transaction
+//! in a fork copies all of its live data.
 
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 use super::{Committed, DataStore, Key, Result};
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct MemoryDataStore {
     // Transaction name -> (key -> data)
-    pending: HashMap<String, HashMap<Key, String>>,
+    pending: HashMap<String, Arc<HashMap<Key, String>>>,
     // Committed (live) data.
-    live: HashMap<Key, String>,
+    live: Arc<HashMap<Key, String>>,
     // Map of data keys to their metadata, which in turn is a mapping of metadata keys to
     // arbitrary (string/serialized) values.
-    metadata: HashMap<Key, HashMap<Key, String>>,
+    metadata: Arc<HashMap<Key, HashMap<Key, String>>>,
 }
 
 impl MemoryDataStore {
@@ -23,17 +29,25 @@ impl MemoryDataStore {
         Default::default()
     }
 
+    /// Returns a copy-on-write clone of the datastore.  No data is copied until the fork or the
+    /// original is modified, and then the whole of the live data, metadata, or pending transaction
+    /// that changed is copied.  Changes to the fork are not visible in the original, and vice
+    /// versa.
+    pub fn fork(&self) -> Self {
+        self.clone()
+    }
+
     fn dataset(&self, committed: &Committed) -> Option<&HashMap<Key, String>> {
         match committed {
-            Committed::Live => Some(&self.live),
-            Committed::Pending { tx } => self.pending.get(tx),
+            Committed::Live => Some(self.live.as_ref()),
+            Committed::Pending { tx } => self.pending.get(tx).map(|pending| pending.as_ref()),
         }
     }
 
     fn dataset_mut(&mut self, committed: &Committed) -> &mut HashMap<Key, String> {
         match committed {
-            Committed::Live => &mut self.live,
-            Committed::Pending { tx } => self.pending.entry(tx.clone()).or_default(),
+            Committed::Live => Arc::make_mut(&mut self.live),
+            Committed::Pending { tx } => Arc::make_mut(self.pending.entry(tx.clone()).or_default()),
         }
     }
 }
@@ -102,7 +116,11 @@ impl DataStore for MemoryDataStore {
     }
 
     fn unset_key(&mut self, key: &Key, committed: &Committed) -> Result<()> {
-        self.dataset_mut(committed).remove(key);
+        // Check first so we don't copy shared data, or create an empty transaction, when there's
+        // nothing to remove.
+        if self.key_populated(key, committed)? {
+            self.dataset_mut(committed).remove(key);
+        }
         Ok(())
     }
 
@@ -127,8 +145,7 @@ impl DataStore for MemoryDataStore {
         value: S,
     ) -> Result<()> {
         // If we don't already have a metadata entry for this data key, insert one.
-        let metadata_for_data = self
-            .metadata
+        let metadata_for_data = Arc::make_mut(&mut self.metadata)
             // Clone data key because we want the HashMap key type to be Key, not &Key, and we
             // can't pass ownership because we only have a reference from our parameters.
             .entry(data_key.clone())
@@ -139,9 +156,12 @@ impl DataStore for MemoryDataStore {
     }
 
     fn unset_metadata(&mut self, metadata_key: &Key, data_key: &Key) -> Result<()> {
-        // If we have any metadata for this data key, remove the given metadata key.
-        if let Some(metadata_for_data) = self.metadata.get_mut(data_key) {
-            metadata_for_data.remove(metadata_key);
+        // If we have any metadata for this data key, remove the given metadata key.  Check first
+        // so we don't copy shared metadata when there's nothing to remove.
+        if self.get_metadata_raw(metadata_key, data_key)?.is_some() {
+            if let Some(metadata_for_data) = Arc::make_mut(&mut self.metadata).get_mut(data_key) {
+                metadata_for_data.remove(metadata_key);
+            }
         }
         Ok(())
     }
@@ -153,7 +173,7 @@ impl DataStore for MemoryDataStore {
         // Remove anything pending for this transaction
         if let Some(pending) = self.pending.remove(transaction.as_ref()) {
             // Apply pending changes to live
-            self.set_keys(&pending, &Committed::Live)?;
+            self.set_keys(pending.as_ref(), &Committed::Live)?;
             // Return keys that were committed
             Ok(pending.keys().cloned().collect())
         } else {
@@ -184,6 +204,7 @@ mod test {
     use super::super::{Committed, DataStore, Key, KeyType};
     use super::MemoryDataStore;
     use maplit::hashset;
+    use std::sync::Arc;
 
     #[test]
     fn get_set_unset() {
@@ -272,4 +293,97 @@ mod test {
         // Assure other transactions were not deleted
         assert!(m.key_populated(&k2, &pending2).unwrap());
     }
+
+    #[test]
+    fn fork() {
+        let mut m = MemoryDataStore::new();
+        let k = Key::new(KeyType::Data, "settings.a.b.c").unwrap();
+        let mdkey = Key::new(KeyType::Meta, "testmd").unwrap();
+        let tx = "test transaction";
+        let pending = Committed::Pending { tx: tx.into() };
+        m.set_key(&k, "original", &Committed::Live).unwrap();
+        m.set_metadata(&mdkey, &k, "original md").unwrap();
+
+        // Apply a pending change over live data in the fork only
+        let mut fork = m.fork();
+        fork.set_key(&k, "forked", &pending).unwrap();
+        fork.commit_transaction(tx).unwrap();
+        fork.set_metadata(&mdkey, &k, "forked md").unwrap();
+
+        assert_eq!(
+            fork.get_key(&k, &Committed::Live).unwrap(),
+            Some("forked".to_string())
+        );
+        assert_eq!(
+            fork.get_metadata_raw(&mdkey, &k).unwrap(),
+            Some("forked md".to_string())
+        );
+
+        // The original is unchanged
+        assert_eq!(
+            m.get_key(&k, &Committed::Live).unwrap(),
+            Some("original".to_string())
+        );
+        assert_eq!(
+            m.get_metadata_raw(&mdkey, &k).unwrap(),
+            Some("original md".to_string())
+        );
+        assert!(m.list_transactions().unwrap().is_empty());
+
+        // And changes to the original aren't visible in the fork
+        m.unset_key(&k, &Committed::Live).unwrap();
+        assert!(fork.key_populated(&k, &Committed::Live).unwrap());
+    }
+
+    #[test]
+    fn unset_missing_key() {
+        let mut m = MemoryDataStore::new();
+        let k = Key::new(KeyType::Data, "settings.a.b.c").unwrap();
+        let missing = Key::new(KeyType::Data, "settings.x.y.z").unwrap();
+        let pending = Committed::Pending {
+            tx: "test transaction".into(),
+        };
+        m.set_key(&k, "value", &Committed::Live).unwrap();
+
+        let mut fork = m.fork();
+        fork.unset_key(&missing, &Committed::Live).unwrap();
+        fork.unset_key(&missing, &pending).unwrap();
+
+        // Live data is still shared, and no transaction was created
+        assert!(Arc::ptr_eq(&m.live, &fork.live));
+        assert!(fork.list_transactions().unwrap().is_empty());
+    }
+
+    #[test]
+    fn fork_commit_copies_live() {
+        let mut m = MemoryDataStore::new();
+        let k = Key::new(KeyType::Data, "settings.a.b.c").unwrap();
+        let k2 = Key::new(KeyType::Data, "settings.x.y.z").unwrap();
+        let tx = "test transaction";
+        let pending = Committed::Pending { tx: tx.into() };
+        let other = Committed::Pending {
+            tx: "other transaction".into(),
+        };
+        m.set_key(&k, "original", &Committed::Live).unwrap();
+        m.set_key(&k2, "other", &other).unwrap();
+        m.set_metadata(&Key::new(KeyType::Meta, "testmd").unwrap(), &k, "md")
+            .unwrap();
+
+        let mut fork = m.fork();
+        fork.set_key(&k, "forked", &pending).unwrap();
+        fork.commit_transaction(tx).unwrap();
+
+        // Committing writes to live, so the fork has its own copy of all live data, but maps it
+        // didn't write to are still shared.
+        assert!(!Arc::ptr_eq(&m.live, &fork.live));
+        assert!(Arc::ptr_eq(&m.metadata, &fork.metadata));
+        assert!(Arc::ptr_eq(
+            &m.pending["other transaction"],
+            &fork.pending["other transaction"]
+        ));
+        assert_eq!(
+            m.get_key(&k, &Committed::Live).unwrap(),
+            Some("original".to_string())
+        );
+    }
 }
@@ -1,74 +1,257 @@
 //! Helpers for parsing arguments common to migrations.
 
-use std::env;
-use std::process;
+use snafu::{ensure, OptionExt};
 
-use crate::{MigrationType, Result};
+use crate::{error, MigrationType, Result};
 
 /// Stores user-supplied arguments.
 pub struct Args {
     pub source_datastore: String,
-    pub target_datastore: String,
+    /// Not needed for dry runs, which don't write any data.
+    pub target_datastore: Option<String>,
     pub migration_type: MigrationType,
+    pub dry_run: bool,
+    /// Release data to make available to migrations as "os" keys, instead of the running host's.
+    /// This lets dry runs check data store snapshots off of a Bottlerocket host.
+    pub os_release: Option<String>,
 }
 
-/// Informs the user about proper usage of the program and exits.
-fn usage() -> ! {
-    let program_name = env::args().next().unwrap_or_else(|| "program".to_string());
-    eprintln!(
-        r"Usage: {}
+/// Describes proper usage of migration programs; included in argument errors.
+pub(crate) const USAGE: &str = r"Usage: MIGRATION
             --source-datastore PATH
             --target-datastore PATH
-            ( --forward | --backward )",
-        program_name
-    );
-    process::exit(2);
-}
+            ( --forward | --backward )
+
+       MIGRATION
+            --dry-run
+            --datastore-path PATH
+            ( --forward | --backward )
+            [ --os-release PATH ]";
 
-/// Prints a more specific message before exiting through usage().
-fn usage_msg<S: AsRef<str>>(msg: S) -> ! {
-    eprintln!("{}\n", msg.as_ref());
-    usage();
+/// Returns the value given after an option, or an error if there isn't one.
+fn option_value<I>(iter: &mut I, option: &str) -> Result<String>
+where
+    I: Iterator<Item = String>,
+{
+    iter.next().context(error::InvalidArgsSnafu {
+        msg: format!("Did not give argument to {}", option),
+    })
 }
 
-/// Parses user arguments into an Args structure.
-pub(crate) fn parse_args(args: env::Args) -> Result<Args> {
+/// Parses user arguments into an Args structure.  The first argument is the program name.
+pub(crate) fn parse_args<I>(args: I) -> Result<Args>
+where
+    I: IntoIterator<Item = String>,
+{
     let mut migration_type = None;
     let mut source_datastore = None;
     let mut target_datastore = None;
+    let mut datastore_path = None;
+    let mut dry_run = false;
+    let mut os_release = None;
 
-    let mut iter = args.skip(1);
+    let mut iter = args.into_iter().skip(1);
     while let Some(arg) = iter.next() {
         match arg.as_ref() {
-            "--source-datastore" => {
-                source_datastore =
-                    Some(iter.next().unwrap_or_else(|| {
-                        usage_msg("Did not give argument to --source-datastore")
-                    }))
-            }
-
-            "--target-datastore" => {
-                target_datastore =
-                    Some(iter.next().unwrap_or_else(|| {
-                        usage_msg("Did not give argument to --target-datastore")
-                    }))
-            }
+            "--source-datastore" => source_datastore = Some(option_value(&mut iter, &arg)?),
+            "--target-datastore" => target_datastore = Some(option_value(&mut iter, &arg)?),
+            // Dry runs only read from the data store, so the path is given on its own.
+            "--datastore-path" => datastore_path = Some(option_value(&mut iter, &arg)?),
+            "--dry-run" => dry_run = true,
+            "--os-release" => os_release = Some(option_value(&mut iter, &arg)?),
 
             "--forward" => migration_type = Some(MigrationType::Forward),
             "--backward" => migration_type = Some(MigrationType::Backward),
 
-            _ => usage(),
+            _ => {
+                return error::InvalidArgsSnafu {
+                    msg: format!("Unknown argument '{}'", arg),
+                }
+                .fail()
+            }
         }
     }
 
-    // In no other case should they be the same; we use it for compatibility checks.
-    if source_datastore == target_datastore {
-        usage_msg("--source-datastore and --target-datastore cannot be the same");
-    }
+    let source_datastore = if dry_run {
+        ensure!(
+            source_datastore.is_none() && target_datastore.is_none(),
+            error::InvalidArgsSnafu {
+                msg: "--source-datastore and --target-datastore cannot be used with --dry-run",
+            }
+        );
+        datastore_path.context(error::InvalidArgsSnafu {
+            msg: "--datastore-path is required with --dry-run",
+        })?
+    } else {
+        ensure!(
+            datastore_path.is_none(),
+            error::InvalidArgsSnafu {
+                msg: "--datastore-path can only be used with --dry-run",
+            }
+        );
+        let source_datastore = source_datastore.context(error::InvalidArgsSnafu {
+            msg: "--source-datastore is required unless --dry-run is given",
+        })?;
+        let target = target_datastore.as_ref().context(error::InvalidArgsSnafu {
+            msg: "--target-datastore is required unless --dry-run is given",
+        })?;
+        // In no other case should they be the same; we use it for compatibility checks.
+        ensure!(
+            &source_datastore != target,
+            error::InvalidArgsSnafu {
+                msg: "--source-datastore and --target-datastore cannot be the same",
+            }
+        );
+        source_datastore
+    };
+
+    let migration_type = migration_type.context(error::InvalidArgsSnafu {
+        msg: "One of --forward or --backward is required",
+    })?;
 
     Ok(Args {
-        source_datastore: source_datastore.unwrap_or_else(|| usage()),
-        target_datastore: target_datastore.unwrap_or_else(|| usage()),
-        migration_type: migration_type.unwrap_or_else(|| usage()),
+        source_datastore,
+        target_datastore,
+        migration_type,
+        dry_run,
+        os_release,
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::parse_args;
+    use crate::error::Error;
+    use crate::MigrationType;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        std::iter::once("migration")
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn migration() {
+        let args = parse_args(args(&[
+            "--source-datastore",
+            "/a",
+            "--target-datastore",
+            "/b",
+            "--forward",
+        ]))
+        .unwrap();
+        assert_eq!(args.source_datastore, "/a");
+        assert_eq!(args.target_datastore.as_deref(), Some("/b"));
+        assert!(matches!(args.migration_type, MigrationType::Forward));
+        assert!(!args.dry_run);
+        assert!(args.os_release.is_none());
+    }
+
+    #[test]
+    fn dry_run() {
+        let args = parse_args(args(&[
+            "--dry-run",
+            "--datastore-path",
+            "/a",
+            "--backward",
+            "--os-release",
+            "/os-release",
+        ]))
+        .unwrap();
+        assert_eq!(args.source_datastore, "/a");
+        assert!(args.target_datastore.is_none());
+        assert!(matches!(args.migration_type, MigrationType::Backward));
+        assert!(args.dry_run);
+        assert_eq!(args.os_release.as_deref(), Some("/os-release"));
+    }
+
+    #[test]
+    fn dry_run_with_target() {
+        let result = parse_args(args(&[
+            "--dry-run",
+            "--datastore-path",
+            "/a",
+            "--target-datastore",
+            "/b",
+            "--forward",
+        ]));
+        assert!(matches!(result, Err(Error::InvalidArgs { .. })));
+    }
+
+    #[test]
+    fn missing_target() {
+        let result = parse_args(args(&["--source-datastore", "/a", "--forward"]));
+        assert!(matches!(result, Err(Error::InvalidArgs { .. })));
+    }
+
+    #[test]
+    fn same_source_and_target() {
+        let result = parse_args(args(&[
+            "--source-datastore",
+            "/a",
+            "--target-datastore",
+            "/a",
+            "--forward",
+        ]));
+        assert!(matches!(result, Err(Error::InvalidArgs { .. })));
+    }
+
+    #[test]
+    fn unknown_argument() {
+        let result = parse_args(args(&["--frobnicate"]));
+        assert!(matches!(result, Err(Error::InvalidArgs { .. })));
+    }
+
+    #[test]
+    fn missing_value() {
+        let result = parse_args(args(&["--forward", "--source-datastore"]));
+        assert!(matches!(result, Err(Error::InvalidArgs { .. })));
+    }
+
+    #[test]
+    fn missing_source() {
+        let result = parse_args(args(&["--target-datastore", "/b", "--forward"]));
+        assert!(matches!(result, Err(Error::InvalidArgs { .. })));
+    }
+
+    #[test]
+    fn missing_migration_type() {
+        let result = parse_args(args(&[
+            "--source-datastore",
+            "/a",
+            "--target-datastore",
+            "/b",
+        ]));
+        assert!(matches!(result, Err(Error::InvalidArgs { .. })));
+    }
+
+    #[test]
+    fn datastore_path_without_dry_run() {
+        let result = parse_args(args(&[
+            "--datastore-path",
+            "/a",
+            "--target-datastore",
+            "/b",
+            "--forward",
+        ]));
+        assert!(matches!(result, Err(Error::InvalidArgs { .. })));
+    }
+
+    #[test]
+    fn dry_run_with_source() {
+        let result = parse_args(args(&[
+            "--dry-run",
+            "--source-datastore",
+            "/a",
+            "--forward",
+        ]));
+        assert!(matches!(result, Err(Error::InvalidArgs { .. })));
+    }
+
+    #[test]
+    fn dry_run_without_path() {
+        let result = parse_args(args(&["--dry-run", "--forward"]));
+        assert!(matches!(result, Err(Error::InvalidArgs { .. })));
+    }
+}
@@ -1,10 +1,72 @@
-use crate::{error, Migration, MigrationData, Result};
-use schnauzer::import::{json_settings::JsonSettingsResolver, StaticHelperResolver};
+use crate::{error, Migration, MigrationData, MigrationType, Result};
+use schnauzer::StaticTemplateImporter;
 use serde::Serialize;
 use shlex::Shlex;
 use snafu::{OptionExt, ResultExt};
 use std::collections::HashMap;
 
+// These helpers do the work of the simpler migrations below.  They take owned or borrowed strings
+// so that declarative migrations, which are loaded at runtime, can share them.
+
+/// Removes the given settings from the migration data.
+pub fn remove_settings<S: AsRef<str>>(input: &mut MigrationData, settings: &[S]) {
+    for setting in settings {
+        let setting = setting.as_ref();
+        if let Some(data) = input.data.remove(setting) {
+            println!("Removed {}, which was set to '{}'", setting, data);
+        } else {
+            println!("Found no {} to remove", setting);
+        }
+    }
+}
+
+/// Removes all settings starting with any of the given prefixes from the migration data.
+pub fn remove_prefixes<S: AsRef<str>>(input: &mut MigrationData, prefixes: &[S]) {
+    let settings = input
+        .data
+        .keys()
+        .filter(|k| prefixes.iter().any(|prefix| k.starts_with(prefix.as_ref())))
+        .cloned()
+        .collect::<Vec<_>>();
+    remove_settings(input, &settings);
+}
+
+/// Changes the string value of the given setting from `from` to `to`.  Settings with any other
+/// value are left alone.  The migration type is only used for logging.
+pub fn replace_string(
+    input: &mut MigrationData,
+    setting: &str,
+    from: &str,
+    to: &str,
+    migration_type: MigrationType,
+) {
+    let direction = match migration_type {
+        MigrationType::Forward => "upgrade",
+        MigrationType::Backward => "downgrade",
+    };
+    match input.data.get_mut(setting) {
+        Some(serde_json::Value::String(data)) if data == from => {
+            to.clone_into(data);
+            println!(
+                "Changed value of '{}' from '{}' to '{}' on {}",
+                setting, from, to, direction
+            );
+        }
+        Some(serde_json::Value::String(_)) => {
+            println!("'{}' is not set to '{}', leaving alone", setting, from);
+        }
+        Some(data) => {
+            println!(
+                "'{}' is set to non-string value '{}'; only string values can be replaced",
+                setting, data
+            );
+        }
+        None => println!("Found no '{}' to change on {}", setting, direction),
+    }
+}
+
+// =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
+
 /// We use this migration when we add settings and want to make sure they're removed before we go
 /// back to old versions that don't understand them.
 pub struct AddSettingsMigration<'a>(pub &'a [&'static str]);
@@ -24,13 +86,7 @@ impl Migration for AddSettingsMigration<'_> {
     /// them and fail deserialization.  (The settings must be defaulted or generated in new versions,
     /// and safe to remove.)
     fn backward(&mut self, mut input: MigrationData) -> Result<MigrationData> {
-        for setting in self.0 {
-            if let Some(data) = input.data.remove(*setting) {
-                println!("Removed {}, which was set to '{}'", setting, data);
-            } else {
-                println!("Found no {} to remove", setting);
-            }
-        }
+        remove_settings(&mut input, self.0);
         Ok(input)
     }
 }
@@ -59,17 +115,7 @@ impl Migration for AddPrefixesMigration {
     /// them and fail deserialization.  (The settings must be defaulted or generated in new versions,
     /// and safe to remove.)
     fn backward(&mut self, mut input: MigrationData) -> Result<MigrationData> {
-        let settings = input
-            .data
-            .keys()
-            .filter(|k| self.0.iter().any(|prefix| k.starts_with(prefix)))
-            .cloned()
-            .collect::<Vec<_>>();
-        for setting in settings {
-            if let Some(data) = input.data.remove(&setting) {
-                println!("Removed {}, which was set to '{}'", setting, data);
-            }
-        }
+        remove_prefixes(&mut input, &self.0);
         Ok(input)
     }
 }
@@ -167,13 +213,7 @@ impl Migration for RemoveSettingsMigration<'_> {
     /// them and fail deserialization.  (The settings must be defaulted or generated in old versions,
     /// and safe to remove.)
     fn forward(&mut self, mut input: MigrationData) -> Result<MigrationData> {
-        for setting in self.0 {
-            if let Some(data) = input.data.remove(*setting) {
-                println!("Removed {}, which was set to '{}'", setting, data);
-            } else {
-                println!("Found no {} to remove", setting);
-            }
-        }
+        remove_settings(&mut input, self.0);
         Ok(input)
     }
 
@@ -199,62 +239,24 @@ pub struct ReplaceStringMigration {
 
 impl Migration for ReplaceStringMigration {
     fn forward(&mut self, mut input: MigrationData) -> Result<MigrationData> {
-        if let Some(data) = input.data.get_mut(self.setting) {
-            match data {
-                serde_json::Value::String(data) => {
-                    if data == self.old_val {
-                        self.new_val.clone_into(data);
-                        println!(
-                            "Changed value of '{}' from '{}' to '{}' on upgrade",
-                            self.setting, self.old_val, self.new_val
-                        );
-                    } else {
-                        println!(
-                            "'{}' is not set to '{}', leaving alone",
-                            self.setting, self.old_val
-                        );
-                    }
-                }
-                _ => {
-                    println!(
-                        "'{}' is set to non-string value '{}'; ReplaceStringMigration only handles strings",
-                        self.setting, data
-                    );
-                }
-            }
-        } else {
-            println!("Found no '{}' to change on upgrade", self.setting);
-        }
+        replace_string(
+            &mut input,
+            self.setting,
+            self.old_val,
+            self.new_val,
+            MigrationType::Forward,
+        );
         Ok(input)
     }
 
     fn backward(&mut self, mut input: MigrationData) -> Result<MigrationData> {
-        if let Some(data) = input.data.get_mut(self.setting) {
-            match data {
-                serde_json::Value::String(data) => {
-                    if data == self.new_val {
-                        self.old_val.clone_into(data);
-                        println!(
-                            "Changed value of '{}' from '{}' to '{}' on downgrade",
-                            self.setting, self.new_val, self.old_val
-                        );
-                    } else {
-                        println!(
-                            "'{}' is not set to '{}', leaving alone",
-                            self.setting, self.new_val
-                        );
-                    }
-                }
-                _ => {
-                    println!(
-                        "'{}' is set to non-string value '{}'; ReplaceStringMigration only handles strings",
-                        self.setting, data
-                    );
-                }
-            }
-        } else {
-            println!("Found no '{}' to change on downgrade", self.setting);
-        }
+        replace_string(
+            &mut input,
+            self.setting,
+            self.new_val,
+            self.old_val,
+            MigrationType::Backward,
+        );
         Ok(input)
     }
 }
@@ -716,30 +718,6 @@ fn structure_migration_data_for_templates(
     Ok(structured_data)
 }
 
-/// Schnauzer's renderer requires a `TemplateImporter` which tells the renderer how to find settings
-/// and helpers. We define our own importer which uses our in-memory copy of the datastore to find
-/// settings, and the current static set of helpers.
-#[derive(Debug, Clone)]
-struct SchnauzerMigrationTemplateImporter {
-    settings_resolver: JsonSettingsResolver,
-    helper_resolver: StaticHelperResolver,
-}
-
-impl SchnauzerMigrationTemplateImporter {
-    fn new(settings: serde_json::Value) -> Self {
-        Self {
-            settings_resolver: JsonSettingsResolver::new(settings),
-            helper_resolver: StaticHelperResolver,
-        }
-    }
-}
-
-schnauzer::impl_template_importer!(
-    SchnauzerMigrationTemplateImporter,
-    JsonSettingsResolver,
-    StaticHelperResolver
-);
-
 impl ReplaceSchnauzerMigration {
     fn get_setting_schnauzer_cmdline(&self, input: &MigrationData) -> Option<String> {
         input
@@ -808,7 +786,10 @@ impl ReplaceSchnauzerMigration {
 
             // Generate settings data using the setting's outgoing template  so we can confirm
             // it matches our expected value; if not, the user has changed it and we should stop.
-            let template_importer = SchnauzerMigrationTemplateImporter::new(input_data);
+            // Schnauzer's renderer requires a `TemplateImporter` which tells the renderer how to
+            // find settings and helpers.  We render offline, using our in-memory copy of the
+            // datastore to find settings, and the current static set of helpers.
+            let template_importer = StaticTemplateImporter::new(input_data);
             let outgoing_command_args = Shlex::new(outgoing_schnauzer_cmdline);
 
             let tokio_runtime = tokio::runtime::Builder::new_multi_thread()
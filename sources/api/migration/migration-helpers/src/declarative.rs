@@ -0,0 +1,431 @@
+//! This module lets simple migrations be described as data rather than code.  Most migrations add,
+//! remove, rename, or replace settings, and writing Rust for each one is a lot of ceremony.  A
+//! `DeclarativeMigration` is loaded from a TOML document listing the operations to perform, and
+//! implements the `Migration` trait like any other migration.  Complex migrations should still
+//! implement `Migration` directly.
+//!
+//! The migration runner still expects a binary per migration, so the document is embedded in the
+//! binary with `include_str!` and passed to `migrate_declarative`.  The migration crate's main.rs
+//! is then the same for every declarative migration, and the change itself lives in
+//! `migration.toml` next to Cargo.toml:
+//!
+//! ```ignore
+//! use migration_helpers::{migrate_declarative, Result};
+//! use std::process;
+//!
+//! const MIGRATION: &str = include_str!("../migration.toml");
+//!
+//! fn run() -> Result<()> {
+//!     migrate_declarative(MIGRATION)
+//! }
+//!
+//! fn main() {
+//!     if let Err(e) = run() {
+//!         eprintln!("{}", e);
+//!         process::exit(1);
+//!     }
+//! }
+//! ```
+//!
+//! Example document:
+//!
+//! ```toml
+//! [[operation]]
+//! type = "add-settings"
+//! settings = ["settings.motd.color"]
+//!
+//! [[operation]]
+//! type = "rename-setting"
+//! old = "settings.motd.colour"
+//! new = "settings.motd.color"
+//!
+//! [[operation]]
+//! type = "replace-string"
+//! setting = "settings.motd.text"
+//! old-value = "hello"
+//! new-value = "hi"
+//! # Only run this operation on these variants.
+//! variants = ["aws-dev", "metal-dev"]
+//! ```
+//!
+//! Operations run in the listed order on upgrade, and in reverse order on downgrade.
+
+use crate::common_migrations::{remove_prefixes, remove_settings, replace_string};
+use crate::{error, Migration, MigrationData, MigrationType, Result};
+use serde::Deserialize;
+use snafu::{ensure, ResultExt};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The data key that holds the running variant, as populated by the migration helpers.
+const VARIANT_KEY: &str = "os.variant_id";
+/// The data key that holds the running architecture, as populated by the migration helpers.
+const ARCH_KEY: &str = "os.arch";
+
+/// A migration described by a TOML document rather than Rust code.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeclarativeMigration {
+    #[serde(rename = "operation")]
+    pub operations: Vec<ConditionalOperation>,
+}
+
+/// An operation, along with the conditions under which it should run.  Conditions are checked
+/// against the "os" data made available to migrations; an operation without conditions always
+/// runs.
+// We don't use `#[serde(flatten)]` for the operation because it disables unknown-field checks,
+// and a misspelled condition would make the operation run everywhere.  Instead, the conditions
+// are removed from the table and the rest must match an `Operation` exactly.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "toml::Table")]
+pub struct ConditionalOperation {
+    pub operation: Operation,
+    /// If given, the operation only runs on one of these variants.
+    pub variants: Option<Vec<String>>,
+    /// If given, the operation only runs on one of these architectures.
+    pub arches: Option<Vec<String>>,
+}
+
+impl TryFrom<toml::Table> for ConditionalOperation {
+    type Error = toml::de::Error;
+
+    fn try_from(mut table: toml::Table) -> std::result::Result<Self, Self::Error> {
+        let variants = table.remove("variants").map(|v| v.try_into()).transpose()?;
+        let arches = table.remove("arches").map(|v| v.try_into()).transpose()?;
+        let operation = toml::Value::Table(table).try_into()?;
+        Ok(Self {
+            operation,
+            variants,
+            arches,
+        })
+    }
+}
+
+/// The types of change a declarative migration can make.  Each mirrors one of the helpers in
+/// `common_migrations`, but owns its data so it can be loaded at runtime.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum Operation {
+    /// Settings were added; they're removed on downgrade.  See `AddSettingsMigration`.
+    AddSettings { settings: Vec<String> },
+    /// Settings under prefixes were added; they're removed on downgrade.  See
+    /// `AddPrefixesMigration`.
+    AddPrefixes { prefixes: Vec<String> },
+    /// Settings were removed; they're removed on upgrade.  See `RemoveSettingsMigration`.
+    RemoveSettings { settings: Vec<String> },
+    /// A setting's string value changed.  See `ReplaceStringMigration`.
+    #[serde(rename_all = "kebab-case")]
+    ReplaceString {
+        setting: String,
+        old_value: String,
+        new_value: String,
+    },
+    /// A setting moved to a new key; its value and metadata move with it.
+    RenameSetting { old: String, new: String },
+}
+
+impl FromStr for DeclarativeMigration {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let migration: Self = toml::from_str(s).context(error::ParseDeclarativeSnafu)?;
+        // An empty document is almost certainly a mistake, and would silently do nothing.
+        ensure!(
+            !migration.operations.is_empty(),
+            error::EmptyDeclarativeSnafu
+        );
+        Ok(migration)
+    }
+}
+
+impl DeclarativeMigration {
+    /// Loads a declarative migration from the TOML file at the given path.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).context(error::ReadDeclarativeSnafu { path })?;
+        contents.parse()
+    }
+}
+
+impl ConditionalOperation {
+    /// Returns whether the operation's conditions are met by the given data.
+    fn applies_to(&self, input: &MigrationData) -> bool {
+        condition_met(&self.variants, input, VARIANT_KEY)
+            && condition_met(&self.arches, input, ARCH_KEY)
+    }
+}
+
+/// Checks whether the string value of `key` is one of the `allowed` values.  No restriction means
+/// the condition is met.
+fn condition_met(allowed: &Option<Vec<String>>, input: &MigrationData, key: &str) -> bool {
+    let allowed = match allowed {
+        Some(allowed) => allowed,
+        None => return true,
+    };
+    match input.data.get(key).and_then(|v| v.as_str()) {
+        Some(actual) => allowed.iter().any(|a| a == actual),
+        None => {
+            println!("Found no '{}' to check operation conditions against", key);
+            false
+        }
+    }
+}
+
+impl Operation {
+    fn forward(&self, input: &mut MigrationData) -> Result<()> {
+        match self {
+            Operation::AddSettings { settings } => {
+                println!("add-settings({:?}) has no work to do on upgrade.", settings);
+            }
+            Operation::AddPrefixes { prefixes } => {
+                println!("add-prefixes({:?}) has no work to do on upgrade.", prefixes);
+            }
+            Operation::RemoveSettings { settings } => remove_settings(input, settings),
+            Operation::ReplaceString {
+                setting,
+                old_value,
+                new_value,
+            } => replace_string(input, setting, old_value, new_value, MigrationType::Forward),
+            Operation::RenameSetting { old, new } => rename_setting(input, old, new)?,
+        }
+        Ok(())
+    }
+
+    fn backward(&self, input: &mut MigrationData) -> Result<()> {
+        match self {
+            Operation::AddSettings { settings } => remove_settings(input, settings),
+            Operation::AddPrefixes { prefixes } => remove_prefixes(input, prefixes),
+            Operation::RemoveSettings { settings } => {
+                println!(
+                    "remove-settings({:?}) has no work to do on downgrade.",
+                    settings
+                );
+            }
+            Operation::ReplaceString {
+                setting,
+                old_value,
+                new_value,
+            } => replace_string(
+                input,
+                setting,
+                new_value,
+                old_value,
+                MigrationType::Backward,
+            ),
+            Operation::RenameSetting { old, new } => rename_setting(input, new, old)?,
+        }
+        Ok(())
+    }
+}
+
+/// Moves a setting and its metadata to a new key.  If the new key is already set, we can't tell
+/// which value is right, and moving back on downgrade would restore the wrong one, so we fail.
+fn rename_setting(input: &mut MigrationData, from: &str, to: &str) -> Result<()> {
+    // Metadata isn't committed, so it may exist even if the data key isn't in this transaction.
+    let data_conflict = input.data.contains_key(from) && input.data.contains_key(to);
+    let metadata_conflict = input.metadata.contains_key(from) && input.metadata.contains_key(to);
+    ensure!(
+        !data_conflict && !metadata_conflict,
+        error::RenameTargetExistsSnafu { from, to }
+    );
+
+    if let Some(data) = input.data.remove(from) {
+        println!("Moved '{}' to '{}'", from, to);
+        input.data.insert(to.to_string(), data);
+    } else {
+        println!("Found no '{}' to move", from);
+    }
+    if let Some(metadata) = input.metadata.remove(from) {
+        println!("Moved metadata of '{}' to '{}'", from, to);
+        input.metadata.insert(to.to_string(), metadata);
+    }
+    Ok(())
+}
+
+impl Migration for DeclarativeMigration {
+    fn forward(&mut self, mut input: MigrationData) -> Result<MigrationData> {
+        for op in &self.operations {
+            if op.applies_to(&input) {
+                op.operation.forward(&mut input)?;
+            } else {
+                println!("Skipping {:?}, conditions not met", op.operation);
+            }
+        }
+        Ok(input)
+    }
+
+    fn backward(&mut self, mut input: MigrationData) -> Result<MigrationData> {
+        for op in self.operations.iter().rev() {
+            if op.applies_to(&input) {
+                op.operation.backward(&mut input)?;
+            } else {
+                println!("Skipping {:?}, conditions not met", op.operation);
+            }
+        }
+        Ok(input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DeclarativeMigration, Operation};
+    use crate::{Migration, MigrationData};
+    use maplit::hashmap;
+    use std::collections::HashMap;
+
+    const DOC: &str = r#"
+        [[operation]]
+        type = "add-settings"
+        settings = ["settings.a.new"]
+
+        [[operation]]
+        type = "rename-setting"
+        old = "settings.a.old-name"
+        new = "settings.a.new-name"
+
+        [[operation]]
+        type = "replace-string"
+        setting = "settings.a.str"
+        old-value = "before"
+        new-value = "after"
+        variants = ["aws-dev"]
+    "#;
+
+    fn data(variant: &str) -> MigrationData {
+        MigrationData {
+            data: hashmap! {
+                "os.variant_id".into() => variant.into(),
+                "settings.a.old-name".into() => 1.into(),
+                "settings.a.str".into() => "before".into(),
+            },
+            metadata: hashmap! {
+                "settings.a.old-name".into() => hashmap! {
+                    "affected-services".into() => "a".into(),
+                },
+            },
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let migration: DeclarativeMigration = DOC.parse().unwrap();
+        assert_eq!(migration.operations.len(), 3);
+        assert_eq!(
+            migration.operations[1].operation,
+            Operation::RenameSetting {
+                old: "settings.a.old-name".into(),
+                new: "settings.a.new-name".into(),
+            }
+        );
+        assert_eq!(
+            migration.operations[2].variants,
+            Some(vec!["aws-dev".to_string()])
+        );
+    }
+
+    #[test]
+    fn parse_unknown_type() {
+        let doc = r#"
+            [[operation]]
+            type = "frobnicate"
+        "#;
+        assert!(doc.parse::<DeclarativeMigration>().is_err());
+    }
+
+    #[test]
+    fn parse_empty() {
+        assert!("".parse::<DeclarativeMigration>().is_err());
+    }
+
+    #[test]
+    fn parse_unknown_condition() {
+        // "variant" instead of "variants"; this must not be ignored, or the operation would run
+        // on every variant.
+        let doc = r#"
+            [[operation]]
+            type = "add-settings"
+            settings = ["settings.a.new"]
+            variant = ["aws-dev"]
+        "#;
+        assert!(doc.parse::<DeclarativeMigration>().is_err());
+    }
+
+    #[test]
+    fn parse_unknown_field() {
+        let doc = r#"
+            [[operation]]
+            type = "replace-string"
+            setting = "settings.a.str"
+            old_value = "before"
+            new-value = "after"
+        "#;
+        assert!(doc.parse::<DeclarativeMigration>().is_err());
+    }
+
+    #[test]
+    fn forward() {
+        let mut migration: DeclarativeMigration = DOC.parse().unwrap();
+        let result = migration.forward(data("aws-dev")).unwrap();
+        assert_eq!(
+            result.data,
+            hashmap! {
+                "os.variant_id".into() => "aws-dev".into(),
+                "settings.a.new-name".into() => 1.into(),
+                "settings.a.str".into() => "after".into(),
+            }
+        );
+        assert!(result.metadata.contains_key("settings.a.new-name"));
+        assert!(!result.metadata.contains_key("settings.a.old-name"));
+    }
+
+    #[test]
+    fn forward_condition_not_met() {
+        let mut migration: DeclarativeMigration = DOC.parse().unwrap();
+        let result = migration.forward(data("metal-dev")).unwrap();
+        assert_eq!(result.data.get("settings.a.str"), Some(&"before".into()));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut migration: DeclarativeMigration = DOC.parse().unwrap();
+        let input = data("aws-dev");
+        let forward = migration.forward(input.clone()).unwrap();
+        let result = migration.backward(forward).unwrap();
+        assert_eq!(result, input);
+    }
+
+    #[test]
+    fn rename_target_exists() {
+        let mut migration: DeclarativeMigration = DOC.parse().unwrap();
+        let mut input = data("aws-dev");
+        input.data.insert("settings.a.new-name".into(), 2.into());
+        assert!(migration.forward(input).is_err());
+    }
+
+    #[test]
+    fn rename_metadata_target_exists() {
+        let mut migration: DeclarativeMigration = DOC.parse().unwrap();
+        let mut input = data("aws-dev");
+        input.metadata.insert(
+            "settings.a.new-name".into(),
+            hashmap! {
+                "affected-services".into() => "b".into(),
+            },
+        );
+        assert!(migration.forward(input).is_err());
+    }
+
+    #[test]
+    fn backward_removes_added() {
+        let mut migration: DeclarativeMigration = DOC.parse().unwrap();
+        let input = MigrationData {
+            data: hashmap! {
+                "settings.a.new".into() => true.into(),
+            },
+            metadata: HashMap::new(),
+        };
+        let result = migration.backward(input).unwrap();
+        assert!(result.data.is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+//! This module compares migration input and output so that a dry run can show what a migration
+//! would change without writing anything to the data store.
+
+use crate::{Metadata, MigrationData};
+use datastore::Value;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// The old and new value of a key that a migration changed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    pub old: Value,
+    pub new: Value,
+}
+
+/// The keys added, removed, and changed in one map of keys to values.  BTreeMaps are used so that
+/// output is sorted and can be compared between runs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct KeyDiff {
+    pub added: BTreeMap<String, Value>,
+    pub removed: BTreeMap<String, Value>,
+    pub changed: BTreeMap<String, Change>,
+}
+
+/// The differences between migration input and output, for data keys and for the metadata of
+/// each data key.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MigrationDiff {
+    pub data: KeyDiff,
+    /// Mapping of data key names to the differences in their metadata.  Data keys with unchanged
+    /// metadata are omitted.
+    pub metadata: BTreeMap<String, KeyDiff>,
+}
+
+impl KeyDiff {
+    fn new(before: &HashMap<String, Value>, after: &HashMap<String, Value>) -> Self {
+        let mut diff = Self::default();
+        for (key, old) in before {
+            match after.get(key) {
+                None => {
+                    diff.removed.insert(key.clone(), old.clone());
+                }
+                Some(new) if new != old => {
+                    diff.changed.insert(
+                        key.clone(),
+                        Change {
+                            old: old.clone(),
+                            new: new.clone(),
+                        },
+                    );
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, new) in after {
+            if !before.contains_key(key) {
+                diff.added.insert(key.clone(), new.clone());
+            }
+        }
+        diff
+    }
+
+    /// Returns true if nothing was added, removed, or changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl MigrationDiff {
+    /// Compares the data given to a migration with the data it returned.
+    pub fn new(before: &MigrationData, after: &MigrationData) -> Self {
+        let data = KeyDiff::new(&before.data, &after.data);
+
+        let empty = Metadata::new();
+        let mut metadata = BTreeMap::new();
+        for data_key in before.metadata.keys().chain(after.metadata.keys()) {
+            if metadata.contains_key(data_key) {
+                continue;
+            }
+            let meta_diff = KeyDiff::new(
+                before.metadata.get(data_key).unwrap_or(&empty),
+                after.metadata.get(data_key).unwrap_or(&empty),
+            );
+            if !meta_diff.is_empty() {
+                metadata.insert(data_key.clone(), meta_diff);
+            }
+        }
+
+        Self { data, metadata }
+    }
+
+    /// Returns true if the migration made no changes.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty() && self.metadata.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Change, MigrationDiff};
+    use crate::MigrationData;
+    use maplit::{btreemap, hashmap};
+    use std::collections::HashMap;
+
+    #[test]
+    fn no_changes() {
+        let data = MigrationData {
+            data: hashmap! {
+                "settings.a".into() => 1.into(),
+            },
+            metadata: hashmap! {
+                "settings.a".into() => hashmap! {
+                    "affected-services".into() => "a".into(),
+                },
+            },
+        };
+        assert!(MigrationDiff::new(&data, &data).is_empty());
+    }
+
+    #[test]
+    fn data_changes() {
+        let before = MigrationData {
+            data: hashmap! {
+                "settings.keep".into() => 1.into(),
+                "settings.change".into() => "old".into(),
+                "settings.remove".into() => true.into(),
+            },
+            metadata: HashMap::new(),
+        };
+        let after = MigrationData {
+            data: hashmap! {
+                "settings.keep".into() => 1.into(),
+                "settings.change".into() => "new".into(),
+                "settings.add".into() => false.into(),
+            },
+            metadata: HashMap::new(),
+        };
+        let diff = MigrationDiff::new(&before, &after);
+        assert_eq!(
+            diff.data.added,
+            btreemap! {"settings.add".into() => false.into()}
+        );
+        assert_eq!(
+            diff.data.removed,
+            btreemap! {"settings.remove".into() => true.into()}
+        );
+        assert_eq!(
+            diff.data.changed,
+            btreemap! {
+                "settings.change".into() => Change { old: "old".into(), new: "new".into() },
+            }
+        );
+        assert!(diff.metadata.is_empty());
+    }
+
+    #[test]
+    fn metadata_changes() {
+        let before = MigrationData {
+            data: HashMap::new(),
+            metadata: hashmap! {
+                "settings.a".into() => hashmap! {
+                    "setting-generator".into() => "old-generator".into(),
+                },
+            },
+        };
+        let after = MigrationData {
+            data: HashMap::new(),
+            metadata: hashmap! {
+                "settings.b".into() => hashmap! {
+                    "setting-generator".into() => "new-generator".into(),
+                },
+            },
+        };
+        let diff = MigrationDiff::new(&before, &after);
+        assert!(diff.data.is_empty());
+        assert_eq!(
+            diff.metadata["settings.a"].removed,
+            btreemap! {"setting-generator".into() => "old-generator".into()}
+        );
+        assert_eq!(
+            diff.metadata["settings.b"].added,
+            btreemap! {"setting-generator".into() => "new-generator".into()}
+        );
+    }
+}
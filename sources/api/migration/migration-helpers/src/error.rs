@@ -53,6 +53,15 @@ pub enum Error {
         source: Box<datastore::Error>,
     },
 
+    #[snafu(display("Invalid arguments: {}\n\n{}", msg, crate::args::USAGE))]
+    InvalidArgs { msg: String },
+
+    #[snafu(display("No target data store given, and not a dry run"))]
+    MissingTargetDatastore,
+
+    #[snafu(display("Unable to serialize dry run diff: {}", source))]
+    SerializeDiff { source: serde_json::Error },
+
     #[snafu(display("Migrated data failed validation: {}", msg))]
     Validation { msg: String },
 
@@ -129,6 +138,21 @@ pub enum Error {
         source: std::io::Error,
     },
 
+    #[snafu(display("Failed to read declarative migration '{}': {}", path.display(), source))]
+    ReadDeclarative {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("Failed to parse declarative migration: {}", source))]
+    ParseDeclarative { source: toml::de::Error },
+
+    #[snafu(display("Declarative migration has no operations"))]
+    EmptyDeclarative,
+
+    #[snafu(display("Unable to rename '{}' to '{}', which is already set", from, to))]
+    RenameTargetExists { from: String, to: String },
+
     #[snafu(display("Failed to create async runtime: {}", source))]
     CreateTokioRuntime { source: std::io::Error },
 }
@@ -12,18 +12,24 @@
 mod args;
 pub mod common_migrations;
 mod datastore_helper;
+pub mod declarative;
+pub mod diff;
 pub mod error;
+pub mod round_trip;
 
-use snafu::ResultExt;
+use snafu::{OptionExt, ResultExt};
 use std::collections::HashMap;
 use std::env;
 use std::fmt;
 
+use bottlerocket_release::BottlerocketRelease;
 use datastore::{Committed, Value};
 pub use datastore::{DataStore, FilesystemDataStore};
 
 use args::{parse_args, Args};
 use datastore_helper::{get_input_data, set_output_data};
+use declarative::DeclarativeMigration;
+use diff::MigrationDiff;
 pub use error::Result;
 
 /// The data store implementation currently in use.  Used by the simpler `migrate` interface; can
@@ -84,9 +90,25 @@ fn validate_migrated_data(_migrated: &MigrationData) -> Result<()> {
 /// If you need a little more control over a migration than with migrate, or you're using this
 /// module as a library, you can call run_migration directly with the arguments that would
 /// normally be parsed from the migration binary's command line.
+///
+/// If `args.dry_run` is set, the migration is run against an in-memory copy of the source data,
+/// and the differences are printed as JSON instead of being written to a target data store.  If
+/// `args.os_release` is set, release data is read from that file rather than the running host, so
+/// dry runs can be done off of a Bottlerocket host.
 pub fn run_migration(mut migration: impl Migration, args: &Args) -> Result<()> {
     let source = DataStoreImplementation::new(&args.source_datastore);
-    let mut target = DataStoreImplementation::new(&args.target_datastore);
+    let mut target = args
+        .target_datastore
+        .as_ref()
+        .map(DataStoreImplementation::new);
+
+    // The release data is made available to migrations as "os" keys.  It normally comes from the
+    // running host, but can be given explicitly, for example to dry-run a snapshot elsewhere.
+    let release = match &args.os_release {
+        Some(path) => BottlerocketRelease::from_file(path),
+        None => BottlerocketRelease::new(),
+    }
+    .context(error::BottlerocketReleaseSnafu)?;
 
     // Run for live data and for each pending transaction
     let mut committeds = vec![Committed::Live];
@@ -96,7 +118,7 @@ pub fn run_migration(mut migration: impl Migration, args: &Args) -> Result<()> {
     committeds.extend(transactions.into_iter().map(|tx| Committed::Pending { tx }));
 
     for committed in committeds {
-        let input = get_input_data(&source, &committed)?;
+        let input = get_input_data(&source, &committed, &release)?;
 
         let mut migrated = input.clone();
         migrated = match args.migration_type {
@@ -106,7 +128,30 @@ pub fn run_migration(mut migration: impl Migration, args: &Args) -> Result<()> {
 
         validate_migrated_data(&migrated)?;
 
-        set_output_data(&mut target, &migrated, &committed)?;
+        if args.dry_run {
+            print_diff(&input, &migrated, &committed)?;
+        } else {
+            let target = target
+                .as_mut()
+                .context(error::MissingTargetDatastoreSnafu)?;
+            set_output_data(target, &migrated, &committed)?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints the changes a migration made to the given data, for dry runs.
+fn print_diff(
+    input: &MigrationData,
+    migrated: &MigrationData,
+    committed: &Committed,
+) -> Result<()> {
+    let diff = MigrationDiff::new(input, migrated);
+    if diff.is_empty() {
+        println!("Dry run: no changes to {:?} data", committed);
+    } else {
+        let output = serde_json::to_string_pretty(&diff).context(error::SerializeDiffSnafu)?;
+        println!("Dry run: changes to {:?} data:\n{}", committed, output);
     }
     Ok(())
 }
@@ -135,3 +180,87 @@ pub fn migrate(migration: impl Migration) -> Result<()> {
     let args = parse_args(env::args())?;
     run_migration(migration, &args)
 }
+
+/// This is the entry point for declarative migrations.  The given TOML document, usually embedded
+/// in the migration binary with `include_str!`, is parsed into a `DeclarativeMigration` and run
+/// like any other migration.  See the `declarative` module for the document format.
+pub fn migrate_declarative(doc: &str) -> Result<()> {
+    let migration: DeclarativeMigration = doc.parse()?;
+    migrate(migration)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{run_migration, Args, MigrationType};
+    use crate::common_migrations::ReplaceStringMigration;
+    use crate::error::Error;
+    use datastore::{Committed, DataStore, FilesystemDataStore, Key, KeyType};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    const OS_RELEASE: &str = r#"PRETTY_NAME="Bottlerocket OS 1.25.0 (aws-dev)"
+VARIANT_ID=aws-dev
+VERSION_ID=1.25.0
+BUILD_ID=abcdef0
+"#;
+
+    /// Creates an empty directory for the named test, with an os-release file and a data store
+    /// containing one setting.
+    fn setup(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("migration-helpers-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("os-release"), OS_RELEASE).unwrap();
+
+        let mut datastore = FilesystemDataStore::new(dir.join("datastore"));
+        datastore
+            .set_key(&setting(), "\"old\"", &Committed::Live)
+            .unwrap();
+        dir
+    }
+
+    fn setting() -> Key {
+        Key::new(KeyType::Data, "settings.a").unwrap()
+    }
+
+    fn args(dir: &Path, dry_run: bool) -> Args {
+        Args {
+            source_datastore: dir.join("datastore").display().to_string(),
+            target_datastore: None,
+            migration_type: MigrationType::Forward,
+            dry_run,
+            os_release: Some(dir.join("os-release").display().to_string()),
+        }
+    }
+
+    fn migration() -> ReplaceStringMigration {
+        ReplaceStringMigration {
+            setting: "settings.a",
+            old_val: "old",
+            new_val: "new",
+        }
+    }
+
+    #[test]
+    fn dry_run() {
+        let dir = setup("dry-run");
+        run_migration(migration(), &args(&dir, true)).unwrap();
+
+        // The source data store is left alone.
+        let datastore = FilesystemDataStore::new(dir.join("datastore"));
+        assert_eq!(
+            datastore.get_key(&setting(), &Committed::Live).unwrap(),
+            Some("\"old\"".to_string())
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_target() {
+        let dir = setup("missing-target");
+        let result = run_migration(migration(), &args(&dir, false));
+        assert!(matches!(result, Err(Error::MissingTargetDatastore)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,225 @@
+//! This module helps migration authors test that their migrations are symmetric.  A migration is
+//! run forward and then backward over sample data, and the result is compared to the original
+//! data; any difference means a downgrade after an upgrade would not restore the user's settings.
+//!
+//! Some migrations are intentionally lossy, for example when metadata is removed on upgrade and
+//! not restored on downgrade because older versions regenerate it.  Those metadata keys can be
+//! listed explicitly with `assert_lossy_round_trip` so the lossiness is documented in the test.
+//!
+//! Migration crates can use these helpers from their tests:
+//!
+//! ```
+//! use migration_helpers::common_migrations::ReplaceStringMigration;
+//! use migration_helpers::round_trip::{assert_round_trip, fixture};
+//!
+//! assert_round_trip(
+//!     &mut ReplaceStringMigration {
+//!         setting: "settings.motd",
+//!         old_val: "hello",
+//!         new_val: "hi",
+//!     },
+//!     &[fixture(&[("settings.motd", "hello".into())], &[])],
+//! );
+//! ```
+
+use crate::diff::MigrationDiff;
+use crate::{Migration, MigrationData, Result};
+use datastore::Value;
+use std::collections::HashMap;
+
+/// Builds migration input from lists of data keys and values, and of data keys, metadata keys,
+/// and metadata values.
+pub fn fixture(data: &[(&str, Value)], metadata: &[(&str, &str, Value)]) -> MigrationData {
+    let data = data
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.clone()))
+        .collect();
+    let mut metadata_map: HashMap<String, HashMap<String, Value>> = HashMap::new();
+    for (data_key, metadata_key, value) in metadata {
+        metadata_map
+            .entry(data_key.to_string())
+            .or_default()
+            .insert(metadata_key.to_string(), value.clone());
+    }
+    MigrationData {
+        data,
+        metadata: metadata_map,
+    }
+}
+
+/// Runs the migration forward and then backward over the input, and returns the differences
+/// between the input and the result.  An empty diff means the migration round-tripped.
+pub fn round_trip(migration: &mut impl Migration, input: &MigrationData) -> Result<MigrationDiff> {
+    let forward = migration.forward(input.clone())?;
+    let backward = migration.backward(forward)?;
+    Ok(MigrationDiff::new(input, &backward))
+}
+
+/// Asserts that each fixture is unchanged after migrating forward and then backward.
+///
+/// # Panics
+///
+/// Panics if the migration fails or if any fixture doesn't round-trip, showing the differences.
+pub fn assert_round_trip(migration: &mut impl Migration, fixtures: &[MigrationData]) {
+    assert_lossy_round_trip(migration, fixtures, &[])
+}
+
+/// Asserts that each fixture is unchanged after migrating forward and then backward, except that
+/// the given metadata, listed as pairs of data key and metadata key, is allowed to be lost.  Data
+/// keys are always checked, as is any other change to metadata.
+///
+/// # Panics
+///
+/// Panics if the migration fails or if any fixture doesn't round-trip, showing the differences.
+pub fn assert_lossy_round_trip(
+    migration: &mut impl Migration,
+    fixtures: &[MigrationData],
+    lost_metadata: &[(&str, &str)],
+) {
+    for (i, fixture) in fixtures.iter().enumerate() {
+        let diff = round_trip(migration, fixture)
+            .unwrap_or_else(|e| panic!("Fixture {} failed to migrate: {}", i, e));
+        let diff = ignore_lost_metadata(diff, lost_metadata);
+        assert!(
+            diff.is_empty(),
+            "Fixture {} did not round-trip; differences:\n{}",
+            i,
+            serde_json::to_string_pretty(&diff).unwrap_or_else(|_| format!("{:?}", diff))
+        );
+    }
+}
+
+/// Removes the given metadata from the metadata removed in the diff.  Only exact pairs of data key
+/// and metadata key are removed, and only if the metadata was lost; other changes still count.
+fn ignore_lost_metadata(mut diff: MigrationDiff, lost_metadata: &[(&str, &str)]) -> MigrationDiff {
+    for (data_key, metadata_key) in lost_metadata {
+        if let Some(meta_diff) = diff.metadata.get_mut(*data_key) {
+            meta_diff.removed.remove(*metadata_key);
+        }
+    }
+    diff.metadata.retain(|_, meta_diff| !meta_diff.is_empty());
+    diff
+}
+
+#[cfg(test)]
+mod test {
+    use super::{assert_lossy_round_trip, assert_round_trip, fixture, round_trip};
+    use crate::common_migrations::{
+        AddSettingsMigration, RemoveMetadataMigration, RemoveSettingsMigration,
+        ReplaceStringMigration, SettingMetadata,
+    };
+
+    #[test]
+    fn symmetric() {
+        let mut migration = ReplaceStringMigration {
+            setting: "settings.a",
+            old_val: "old",
+            new_val: "new",
+        };
+        assert_round_trip(
+            &mut migration,
+            &[
+                fixture(&[("settings.a", "old".into())], &[]),
+                fixture(&[("settings.a", "custom".into())], &[]),
+                fixture(&[], &[]),
+            ],
+        );
+    }
+
+    #[test]
+    fn asymmetric() {
+        let mut migration = RemoveMetadataMigration(&[SettingMetadata {
+            setting: "settings.a",
+            metadata: &["setting-generator"],
+        }]);
+        let input = fixture(
+            &[("settings.a", 1.into())],
+            &[("settings.a", "setting-generator", "generator".into())],
+        );
+        let diff = round_trip(&mut migration, &input).unwrap();
+        assert!(diff.data.is_empty());
+        assert!(diff.metadata["settings.a"]
+            .removed
+            .contains_key("setting-generator"));
+    }
+
+    #[test]
+    #[should_panic(expected = "did not round-trip")]
+    fn asymmetric_panics() {
+        let mut migration = RemoveMetadataMigration(&[SettingMetadata {
+            setting: "settings.a",
+            metadata: &["setting-generator"],
+        }]);
+        assert_round_trip(
+            &mut migration,
+            &[fixture(
+                &[],
+                &[("settings.a", "setting-generator", "generator".into())],
+            )],
+        );
+    }
+
+    #[test]
+    fn documented_lossiness() {
+        let mut migration = RemoveMetadataMigration(&[SettingMetadata {
+            setting: "settings.a",
+            metadata: &["setting-generator"],
+        }]);
+        assert_lossy_round_trip(
+            &mut migration,
+            &[fixture(
+                &[],
+                &[("settings.a", "setting-generator", "generator".into())],
+            )],
+            &[("settings.a", "setting-generator")],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "did not round-trip")]
+    fn lossiness_is_specific() {
+        // Only the listed metadata may be lost; the data key, and other metadata on it, must still
+        // round-trip.
+        let mut migration = RemoveMetadataMigration(&[SettingMetadata {
+            setting: "settings.a",
+            metadata: &["setting-generator", "affected-services"],
+        }]);
+        assert_lossy_round_trip(
+            &mut migration,
+            &[fixture(
+                &[("settings.a", 1.into())],
+                &[
+                    ("settings.a", "setting-generator", "generator".into()),
+                    ("settings.a", "affected-services", vec!["a"].into()),
+                ],
+            )],
+            &[("settings.a", "setting-generator")],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "did not round-trip")]
+    fn lost_data_is_not_ignored() {
+        // A migration that loses the data itself isn't excused by listing its metadata.
+        let mut migration = RemoveSettingsMigration(&["settings.a"]);
+        assert_lossy_round_trip(
+            &mut migration,
+            &[fixture(
+                &[("settings.a", 1.into())],
+                &[("settings.a", "setting-generator", "generator".into())],
+            )],
+            &[("settings.a", "setting-generator")],
+        );
+    }
+
+    #[test]
+    fn added_settings_from_old_data() {
+        // Old data doesn't have the new setting, so it round-trips even though the setting is
+        // removed on downgrade.
+        let mut migration = AddSettingsMigration(&["settings.new"]);
+        assert_round_trip(
+            &mut migration,
+            &[fixture(&[("settings.old", true.into())], &[])],
+        );
+    }
+}
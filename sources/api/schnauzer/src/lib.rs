@@ -46,6 +46,10 @@
 //! extensions to resolve settings and helper data; however, custom `TemplateImporter`
 //! implementations can be used as well.
 //!
+//! To render templates offline, without access to the Bottlerocket API, use
+//! `schnauzer::StaticTemplateImporter`, which resolves settings from a static JSON document and
+//! provides the same helpers as `BottlerocketTemplateImporter`.
+//!
 //! For static datasets to be used for tests, enable the `testfakes` feature in `Cargo.toml`.
 //!
 //! ## The schnauzer Settings Generator
@@ -60,6 +64,13 @@
 //!   --template 'foo-{{ myhelper settings.bar }}'
 //! ```
 //!
+//! Templates can be rendered offline by passing a JSON settings document, in the same form as the
+//! data given to templates, instead of reading settings from the API:
+//!
+//! ```bash
+//! schnauzer-v2 --settings-file settings.json render-file --path my-template
+//! ```
+//!
 //! ## schnauzer v1
 //! schnauzer was originally written to render simpler templates which always had the full scope of
 //! Bottlerocket settings and helper functions available to them, making it incompatible with the
@@ -123,5 +134,5 @@ pub use v2::import::fake::{FakeHelperResolver, FakeImporter, FakeSettingsResolve
 
 pub use v2::{
     error::RenderError, import, render_template, render_template_file, template,
-    BottlerocketTemplateImporter,
+    BottlerocketTemplateImporter, StaticTemplateImporter,
 };
@@ -6,12 +6,13 @@ use crate::import::{HelperResolver, SettingsResolver, TemplateImporter};
 use crate::{
     render_template, render_template_file,
     template::{ExtensionRequirement, Template, TemplateFrontmatter},
-    BottlerocketTemplateImporter,
+    BottlerocketTemplateImporter, StaticTemplateImporter,
 };
 use argh::FromArgs;
 use simplelog::{Config as LogConfig, LevelFilter, SimpleLogger};
-use snafu::{OptionExt, ResultExt};
-use std::path::PathBuf;
+use snafu::{ensure, OptionExt, ResultExt};
+use std::fs;
+use std::path::{Path, PathBuf};
 
 mod clirequirements;
 
@@ -26,6 +27,10 @@ struct Args {
     #[argh(option, default = "constants::API_SOCKET.into()")]
     api_socket: PathBuf,
 
+    /// path to a JSON settings document to render with, instead of the Bottlerocket API
+    #[argh(option)]
+    settings_file: Option<PathBuf>,
+
     #[argh(subcommand)]
     subcommand: Subcommand,
 }
@@ -97,6 +102,8 @@ where
 
 /// Run the schnauzer-v2 CLI, parsing arguments from the given set of strings and a given template
 /// importer.
+///
+/// The given importer provides the settings, so `--settings-file` is rejected rather than ignored.
 pub async fn run_with_args<I, T, SR, HR>(
     iter: I,
     template_importer: &dyn TemplateImporter<SettingsResolver = SR, HelperResolver = HR>,
@@ -118,20 +125,42 @@ where
             parser_output: e.output,
         })?;
 
+    ensure!(
+        args.settings_file.is_none(),
+        error::SettingsFileWithImporterSnafu
+    );
+
     run_with_parsed_args(args, template_importer).await
 }
 
+/// Creates a StaticTemplateImporter using the settings in the given JSON file, which has the same
+/// structure as the data given to templates, e.g. `{"settings": {...}, "os": {...}}`.
+pub fn static_importer_from_file(path: &Path) -> Result<StaticTemplateImporter> {
+    let settings_str = fs::read_to_string(path).context(error::ReadSettingsFileSnafu { path })?;
+    let settings =
+        serde_json::from_str(&settings_str).context(error::ParseSettingsFileSnafu { path })?;
+    Ok(StaticTemplateImporter::new(settings))
+}
+
 /// Run the schnauzer-v2 CLI, parsing arguments from the command line.
 ///
 /// Uses the BottlerocketTemplateImporter, which reads settings and helpers from the Bottlerocket
-/// API.
+/// API, unless a settings file is given, in which case the StaticTemplateImporter renders
+/// offline using the settings from that file.
 pub async fn run() -> Result<()> {
     let args: Args = argh::from_env();
     SimpleLogger::init(args.log_level, LogConfig::default()).context(error::LoggingSetupSnafu)?;
 
-    let template_importer = BottlerocketTemplateImporter::new(args.api_socket.clone());
-
-    let rendered_template = run_with_parsed_args(args, &template_importer).await?;
+    let rendered_template = match &args.settings_file {
+        Some(path) => {
+            let template_importer = static_importer_from_file(path)?;
+            run_with_parsed_args(args, &template_importer).await?
+        }
+        None => {
+            let template_importer = BottlerocketTemplateImporter::new(args.api_socket.clone());
+            run_with_parsed_args(args, &template_importer).await?
+        }
+    };
 
     println!(
         "{}",
@@ -142,6 +171,7 @@ pub async fn run() -> Result<()> {
 
 pub mod error {
     use snafu::Snafu;
+    use std::path::PathBuf;
 
     #[derive(Debug, Snafu)]
     #[snafu(visibility(pub))]
@@ -161,9 +191,26 @@ pub mod error {
         #[snafu(display("Failed to parse CLI arguments: No CLI command given"))]
         ParseCLICommand,
 
+        #[snafu(display("Failed to parse settings file '{}': '{}'", path.display(), source))]
+        ParseSettingsFile {
+            path: PathBuf,
+            source: serde_json::Error,
+        },
+
+        #[snafu(display("Failed to read settings file '{}': '{}'", path.display(), source))]
+        ReadSettingsFile {
+            path: PathBuf,
+            source: std::io::Error,
+        },
+
         #[snafu(display("Failed to render template: '{}'", source))]
         RenderTemplate { source: crate::RenderError },
 
+        #[snafu(display(
+            "Cannot use a settings file when rendering with a given template importer"
+        ))]
+        SettingsFileWithImporter,
+
         #[snafu(display(
             "Could not parse extension requirement from '{}': {}'",
             requirement,
@@ -60,6 +60,33 @@ impl_template_importer!(
     StaticHelperResolver
 );
 
+/// A `TemplateImporter` that renders templates offline, using a static settings document rather
+/// than the Bottlerocket API, along with the same helpers as `BottlerocketTemplateImporter`.
+///
+/// The settings document has the same structure as the data given to templates, e.g.
+/// `{"settings": {...}, "os": {...}}`.  This is useful for rendering templates in tests, CI, or
+/// migrations, where the API isn't available.
+#[derive(Debug, Clone)]
+pub struct StaticTemplateImporter {
+    settings_resolver: JsonSettingsResolver,
+    helper_resolver: StaticHelperResolver,
+}
+
+impl StaticTemplateImporter {
+    pub fn new(settings: serde_json::Value) -> Self {
+        Self {
+            settings_resolver: JsonSettingsResolver::new(settings),
+            helper_resolver: StaticHelperResolver,
+        }
+    }
+}
+
+impl_template_importer!(
+    StaticTemplateImporter,
+    JsonSettingsResolver,
+    StaticHelperResolver
+);
+
 /// Utility that Boxes an error type to be returned by a generic trait interface.
 ///
 /// Intended to be called as e.g. `fallible().map_err(as_std_err)`
@@ -10,7 +10,7 @@ pub mod template;
 #[cfg(feature = "testfakes")]
 pub use self::import::fake;
 
-pub use self::import::BottlerocketTemplateImporter;
+pub use self::import::{BottlerocketTemplateImporter, StaticTemplateImporter};
 pub use template::{ExtensionRequirement, Template, TemplateFrontmatter};
 
 /// Renders a Bottlerocket config template
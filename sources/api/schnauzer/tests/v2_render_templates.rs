@@ -179,3 +179,101 @@ async fn render_07_aws_config() {
         expected_render
     );
 }
+
+#[tokio::test]
+async fn render_07_aws_config_offline() {
+    let template = include_str!("./templates/succeeds/07_aws-config.template");
+
+    let expected_render = include_str!("./templates/succeeds_rendered/07_aws-config.rendered");
+
+    // The static importer provides the real helpers, so none need to be faked.
+    let importer = schnauzer::StaticTemplateImporter::new(json!({
+        "settings": {
+            "aws": {
+                "config": "c2V0dGluZ3MK"
+            }
+        }
+    }));
+
+    assert_eq!(
+        schnauzer::v2::render_template_str(&importer, template)
+            .await
+            .unwrap(),
+        expected_render
+    );
+}
+
+#[tokio::test]
+async fn run_with_args_rejects_settings_file() {
+    let importer = schnauzer::StaticTemplateImporter::new(json!({}));
+    let result = schnauzer::v2::cli::run_with_args(
+        [
+            "schnauzer-v2",
+            "--settings-file",
+            "settings.json",
+            "render",
+            "--template",
+            "hi",
+        ],
+        &importer,
+    )
+    .await;
+
+    assert!(matches!(
+        result,
+        Err(schnauzer::v2::cli::CLIError::SettingsFileWithImporter)
+    ));
+}
+
+/// Writes the given contents to a file in a directory unique to the named test.
+fn settings_file(name: &str, contents: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("schnauzer-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("settings.json");
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[tokio::test]
+async fn render_07_aws_config_settings_file() {
+    // Render entirely from disk, as `schnauzer-v2 --settings-file ... render-file` does.
+    let template_path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/templates/succeeds/07_aws-config.template"
+    );
+
+    let expected_render = include_str!("./templates/succeeds_rendered/07_aws-config.rendered");
+
+    let path = settings_file(
+        "settings-file",
+        r#"{"settings": {"aws": {"config": "c2V0dGluZ3MK"}}}"#,
+    );
+    let importer = schnauzer::v2::cli::static_importer_from_file(&path).unwrap();
+
+    assert_eq!(
+        schnauzer::v2::render_template_file(&importer, template_path)
+            .await
+            .unwrap(),
+        expected_render
+    );
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn settings_file_missing() {
+    let path = std::env::temp_dir().join("schnauzer-missing-settings-file.json");
+    assert!(matches!(
+        schnauzer::v2::cli::static_importer_from_file(&path),
+        Err(schnauzer::v2::cli::CLIError::ReadSettingsFile { .. })
+    ));
+}
+
+#[test]
+fn settings_file_malformed() {
+    let path = settings_file("settings-file-malformed", r#"{"settings": "#);
+    assert!(matches!(
+        schnauzer::v2::cli::static_importer_from_file(&path),
+        Err(schnauzer::v2::cli::CLIError::ParseSettingsFile { .. })
+    ));
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
@@ -4,10 +4,12 @@ use std::process;
 
 /// We added one new settings for configuring the override method for the method used to determine
 /// the node
+fn migration() -> AddSettingsMigration<'static> {
+    AddSettingsMigration(&["settings.kubernetes.hostname-override-source"])
+}
+
 fn run() -> Result<()> {
-    migrate(AddSettingsMigration(&[
-        "settings.kubernetes.hostname-override-source",
-    ]))
+    migrate(migration())
 }
 
 // Returning a Result from main makes it print a Debug representation of the error, but with Snafu
@@ -19,3 +21,21 @@ fn main() {
         process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::migration;
+    use migration_helpers::round_trip::{assert_round_trip, fixture};
+
+    #[test]
+    fn round_trip() {
+        // Data from the prior version doesn't have the new setting.
+        assert_round_trip(
+            &mut migration(),
+            &[
+                fixture(&[("settings.kubernetes.cluster-name", "c".into())], &[]),
+                fixture(&[], &[]),
+            ],
+        );
+    }
+}
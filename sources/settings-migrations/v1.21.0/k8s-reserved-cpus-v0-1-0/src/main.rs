@@ -3,8 +3,12 @@ use migration_helpers::{migrate, Result};
 use std::process;
 
 /// Add the option to set Kubernetes reserved-cpus
+fn migration() -> AddSettingsMigration<'static> {
+    AddSettingsMigration(&["settings.kubernetes.reserved-cpus"])
+}
+
 fn run() -> Result<()> {
-    migrate(AddSettingsMigration(&["settings.kubernetes.reserved-cpus"]))
+    migrate(migration())
 }
 
 // Returning a Result from main makes it print a Debug representation of the error, but with Snafu
@@ -16,3 +20,21 @@ fn main() {
         process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::migration;
+    use migration_helpers::round_trip::{assert_round_trip, fixture};
+
+    #[test]
+    fn round_trip() {
+        // Data from the prior version doesn't have the new setting.
+        assert_round_trip(
+            &mut migration(),
+            &[
+                fixture(&[("settings.kubernetes.cluster-name", "c".into())], &[]),
+                fixture(&[], &[]),
+            ],
+        );
+    }
+}
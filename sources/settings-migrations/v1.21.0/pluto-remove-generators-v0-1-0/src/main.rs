@@ -1,8 +1,8 @@
 use migration_helpers::common_migrations::{RemoveMetadataMigration, SettingMetadata};
 use migration_helpers::{migrate, Result};
 use std::process;
-fn run() -> Result<()> {
-    migrate(RemoveMetadataMigration(&[
+fn migration() -> RemoveMetadataMigration {
+    RemoveMetadataMigration(&[
         SettingMetadata {
             setting: "settings.kubernetes.max-pods",
             metadata: &["setting-generator"],
@@ -23,7 +23,11 @@ fn run() -> Result<()> {
             setting: "settings.kubernetes.hostname-override",
             metadata: &["setting-generator"],
         },
-    ]))
+    ])
+}
+
+fn run() -> Result<()> {
+    migrate(migration())
 }
 fn main() {
     if let Err(e) = run() {
@@ -31,3 +35,40 @@ fn main() {
         process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::migration;
+    use migration_helpers::round_trip::{assert_lossy_round_trip, fixture};
+
+    const SETTINGS: &[&str] = &[
+        "settings.kubernetes.max-pods",
+        "settings.kubernetes.cluster-dns-ip",
+        "settings.kubernetes.node-ip",
+        "settings.kubernetes.provider-id",
+        "settings.kubernetes.hostname-override",
+    ];
+
+    #[test]
+    fn round_trip() {
+        // The generators are removed on upgrade and not restored on downgrade; older versions
+        // have them in their defaults, so the metadata is expected to be lost.
+        let data = SETTINGS
+            .iter()
+            .map(|setting| (*setting, "generated".into()))
+            .collect::<Vec<_>>();
+        let metadata = SETTINGS
+            .iter()
+            .map(|setting| (*setting, "setting-generator", "pluto".into()))
+            .collect::<Vec<_>>();
+        let lost = SETTINGS
+            .iter()
+            .map(|setting| (*setting, "setting-generator"))
+            .collect::<Vec<_>>();
+        assert_lossy_round_trip(
+            &mut migration(),
+            &[fixture(&data, &metadata), fixture(&[], &[])],
+            &lost,
+        );
+    }
+}
@@ -4,15 +4,17 @@ use migration_helpers::common_migrations::{
 use migration_helpers::{migrate, Result};
 use std::process;
 
+fn migration() -> ReplaceMetadataListsMigration {
+    ReplaceMetadataListsMigration(vec![MetadataListReplacement {
+        setting: "settings.kubernetes.pod-infra-container-image",
+        metadata: "affected-services",
+        old_vals: &["kubernetes", "containerd"],
+        new_vals: &["pod-infra-container-image"],
+    }])
+}
+
 fn run() -> Result<()> {
-    migrate(ReplaceMetadataListsMigration(vec![
-        MetadataListReplacement {
-            setting: "settings.kubernetes.pod-infra-container-image",
-            metadata: "affected-services",
-            old_vals: &["kubernetes", "containerd"],
-            new_vals: &["pod-infra-container-image"],
-        },
-    ]))
+    migrate(migration())
 }
 
 // Returning a Result from main makes it print a Debug representation of the error, but with Snafu
@@ -24,3 +26,33 @@ fn main() {
         process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::migration;
+    use migration_helpers::round_trip::{assert_round_trip, fixture};
+
+    const SETTING: &str = "settings.kubernetes.pod-infra-container-image";
+
+    #[test]
+    fn round_trip() {
+        assert_round_trip(
+            &mut migration(),
+            &[
+                fixture(
+                    &[(SETTING, "example.com/pause:latest".into())],
+                    &[(
+                        SETTING,
+                        "affected-services",
+                        vec!["kubernetes", "containerd"].into(),
+                    )],
+                ),
+                fixture(
+                    &[(SETTING, "example.com/pause:latest".into())],
+                    &[(SETTING, "affected-services", vec!["custom"].into())],
+                ),
+                fixture(&[], &[]),
+            ],
+        );
+    }
+}
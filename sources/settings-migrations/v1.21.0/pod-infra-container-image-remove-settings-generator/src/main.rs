@@ -2,11 +2,15 @@ use migration_helpers::common_migrations::{RemoveMetadataMigration, SettingMetad
 use migration_helpers::{migrate, Result};
 use std::process;
 
-fn run() -> Result<()> {
-    migrate(RemoveMetadataMigration(&[SettingMetadata {
+fn migration() -> RemoveMetadataMigration {
+    RemoveMetadataMigration(&[SettingMetadata {
         setting: "settings.kubernetes.pod-infra-container-image",
         metadata: &["setting-generator"],
-    }]))
+    }])
+}
+
+fn run() -> Result<()> {
+    migrate(migration())
 }
 
 // Returning a Result from main makes it print a Debug representation of the error, but with Snafu
@@ -18,3 +22,32 @@ fn main() {
         process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::migration;
+    use migration_helpers::round_trip::{assert_lossy_round_trip, fixture};
+
+    const SETTING: &str = "settings.kubernetes.pod-infra-container-image";
+
+    #[test]
+    fn round_trip() {
+        // The generator is removed on upgrade and not restored on downgrade; older versions have
+        // it in their defaults, so the metadata is expected to be lost.
+        assert_lossy_round_trip(
+            &mut migration(),
+            &[
+                fixture(
+                    &[(SETTING, "example.com/pause:latest".into())],
+                    &[(
+                        SETTING,
+                        "setting-generator",
+                        "pluto pod-infra-container-image".into(),
+                    )],
+                ),
+                fixture(&[], &[]),
+            ],
+            &[(SETTING, "setting-generator")],
+        );
+    }
+}
@@ -2,11 +2,15 @@ use migration_helpers::common_migrations::AddPrefixesMigration;
 use migration_helpers::{migrate, Result};
 use std::process;
 
-fn run() -> Result<()> {
-    migrate(AddPrefixesMigration(vec![
+fn migration() -> AddPrefixesMigration {
+    AddPrefixesMigration(vec![
         "services.pod-infra-container-image",
         "configuration-files.pod-infra-container-image-log-message",
-    ]))
+    ])
+}
+
+fn run() -> Result<()> {
+    migrate(migration())
 }
 
 // Returning a Result from main makes it print a Debug representation of the error, but with Snafu
@@ -18,3 +22,24 @@ fn main() {
         process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::migration;
+    use migration_helpers::round_trip::{assert_round_trip, fixture};
+
+    #[test]
+    fn round_trip() {
+        // Data from the prior version doesn't have the new service or configuration file.
+        assert_round_trip(
+            &mut migration(),
+            &[
+                fixture(
+                    &[("services.kubernetes.restart-commands", vec!["a"].into())],
+                    &[],
+                ),
+                fixture(&[], &[]),
+            ],
+        );
+    }
+}
@@ -6,12 +6,16 @@ const OLD_ADMIN_CTR_SOURCE_VAL: &str = "public.ecr.aws/bottlerocket/bottlerocket
 const NEW_ADMIN_CTR_SOURCE_VAL: &str = "public.ecr.aws/bottlerocket/bottlerocket-admin:v0.11.10";
 
 /// We bumped the version of the default admin container
-fn run() -> Result<()> {
-    migrate(ReplaceStringMigration {
+fn migration() -> ReplaceStringMigration {
+    ReplaceStringMigration {
         setting: "settings.host-containers.admin.source",
         old_val: OLD_ADMIN_CTR_SOURCE_VAL,
         new_val: NEW_ADMIN_CTR_SOURCE_VAL,
-    })
+    }
+}
+
+fn run() -> Result<()> {
+    migrate(migration())
 }
 
 // Returning a Result from main makes it print a Debug representation of the error, but with Snafu
@@ -23,3 +27,30 @@ fn main() {
         process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{migration, OLD_ADMIN_CTR_SOURCE_VAL};
+    use migration_helpers::round_trip::{assert_round_trip, fixture};
+
+    #[test]
+    fn round_trip() {
+        assert_round_trip(
+            &mut migration(),
+            &[
+                fixture(
+                    &[(
+                        "settings.host-containers.admin.source",
+                        OLD_ADMIN_CTR_SOURCE_VAL.into(),
+                    )],
+                    &[],
+                ),
+                fixture(
+                    &[("settings.host-containers.admin.source", "custom".into())],
+                    &[],
+                ),
+                fixture(&[], &[]),
+            ],
+        );
+    }
+}
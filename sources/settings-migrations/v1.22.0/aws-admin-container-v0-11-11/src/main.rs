@@ -8,12 +8,16 @@ const NEW_ADMIN_CTR_CMDLINE: &str =
     "schnauzer-v2 render --requires 'aws@v1(helpers=[ecr-prefix])' --template '{{ ecr-prefix settings.aws.region }}/bottlerocket-admin:v0.11.11'";
 
 /// We bumped the version of the default admin container
-fn run() -> Result<()> {
-    migrate(ReplaceSchnauzerMigration {
+fn migration() -> ReplaceSchnauzerMigration {
+    ReplaceSchnauzerMigration {
         setting: "settings.host-containers.admin.source",
         old_schnauzer_cmdline: OLD_ADMIN_CTR_CMDLINE,
         new_schnauzer_cmdline: NEW_ADMIN_CTR_CMDLINE,
-    })
+    }
+}
+
+fn run() -> Result<()> {
+    migrate(migration())
 }
 
 // Returning a Result from main makes it print a Debug representation of the error, but with Snafu
@@ -25,3 +29,41 @@ fn main() {
         process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{migration, OLD_ADMIN_CTR_CMDLINE};
+    use migration_helpers::round_trip::{assert_round_trip, fixture};
+
+    const SETTING: &str = "settings.host-containers.admin.source";
+
+    #[test]
+    fn round_trip() {
+        assert_round_trip(
+            &mut migration(),
+            &[
+                // Generated from the prior default
+                fixture(
+                    &[
+                        ("settings.aws.region", "us-west-2".into()),
+                        (
+                            SETTING,
+                            "328549459982.dkr.ecr.us-west-2.amazonaws.com/bottlerocket-admin:v0.11.10"
+                                .into(),
+                        ),
+                    ],
+                    &[(SETTING, "setting-generator", OLD_ADMIN_CTR_CMDLINE.into())],
+                ),
+                // Customized by the user
+                fixture(
+                    &[
+                        ("settings.aws.region", "us-west-2".into()),
+                        (SETTING, "example.com/custom:latest".into()),
+                    ],
+                    &[(SETTING, "setting-generator", OLD_ADMIN_CTR_CMDLINE.into())],
+                ),
+                fixture(&[], &[]),
+            ],
+        );
+    }
+}
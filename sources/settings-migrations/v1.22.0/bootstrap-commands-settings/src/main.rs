@@ -2,12 +2,16 @@ use migration_helpers::common_migrations::AddPrefixesMigration;
 use migration_helpers::{migrate, Result};
 use std::process;
 
-fn run() -> Result<()> {
-    migrate(AddPrefixesMigration(vec![
+fn migration() -> AddPrefixesMigration {
+    AddPrefixesMigration(vec![
         "settings.bootstrap-commands",
         "services.bootstrap-commands",
         "configuration-files.bootstrap-commands-toml",
-    ]))
+    ])
+}
+
+fn run() -> Result<()> {
+    migrate(migration())
 }
 
 fn main() {
@@ -16,3 +20,21 @@ fn main() {
         process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::migration;
+    use migration_helpers::round_trip::{assert_round_trip, fixture};
+
+    #[test]
+    fn round_trip() {
+        // Data from the prior version doesn't have the new settings.
+        assert_round_trip(
+            &mut migration(),
+            &[
+                fixture(&[("settings.motd", "hi".into())], &[]),
+                fixture(&[], &[]),
+            ],
+        );
+    }
+}
@@ -3,13 +3,17 @@ use migration_helpers::{migrate, Result};
 use std::process;
 
 /// We added a new setting for configuring the NVIDIA k8s device plugin.
-fn run() -> Result<()> {
-    migrate(AddPrefixesMigration(vec![
+fn migration() -> AddPrefixesMigration {
+    AddPrefixesMigration(vec![
         "settings.kubelet-device-plugins",
         "services.nvidia-k8s-device-plugin",
         "configuration-files.nvidia-k8s-device-plugin-conf",
         "configuration-files.nvidia-k8s-device-plugin-exec-start-conf",
-    ]))
+    ])
+}
+
+fn run() -> Result<()> {
+    migrate(migration())
 }
 
 // Returning a Result from main makes it print a Debug representation of the error, but with Snafu
@@ -21,3 +25,21 @@ fn main() {
         process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::migration;
+    use migration_helpers::round_trip::{assert_round_trip, fixture};
+
+    #[test]
+    fn round_trip() {
+        // Data from the prior version doesn't have the new settings.
+        assert_round_trip(
+            &mut migration(),
+            &[
+                fixture(&[("settings.motd", "hi".into())], &[]),
+                fixture(&[], &[]),
+            ],
+        );
+    }
+}
@@ -3,11 +3,15 @@ use migration_helpers::{migrate, Result};
 use std::process;
 
 /// We added a new setting for configuring container runtime (containerd) settings only for NVIDIA k8s variants.
-fn run() -> Result<()> {
-    migrate(AddMetadataMigration(&[SettingMetadata {
+fn migration() -> AddMetadataMigration {
+    AddMetadataMigration(&[SettingMetadata {
         metadata: &["affected-services"],
         setting: "settings.nvidia-container-runtime",
-    }]))
+    }])
+}
+
+fn run() -> Result<()> {
+    migrate(migration())
 }
 
 // Returning a Result from main makes it print a Debug representation of the error, but with Snafu
@@ -19,3 +23,24 @@ fn main() {
         process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::migration;
+    use migration_helpers::round_trip::{assert_round_trip, fixture};
+
+    #[test]
+    fn round_trip() {
+        // Data from the prior version doesn't have metadata for the new setting.
+        assert_round_trip(
+            &mut migration(),
+            &[
+                fixture(
+                    &[],
+                    &[("settings.motd", "affected-services", vec!["motd"].into())],
+                ),
+                fixture(&[], &[]),
+            ],
+        );
+    }
+}
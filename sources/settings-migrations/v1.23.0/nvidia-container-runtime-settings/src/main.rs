@@ -3,12 +3,16 @@ use migration_helpers::{migrate, Result};
 use std::process;
 
 /// We added a new setting for configuring container runtime (containerd) settings only for NVIDIA k8s variants.
-fn run() -> Result<()> {
-    migrate(AddPrefixesMigration(vec![
+fn migration() -> AddPrefixesMigration {
+    AddPrefixesMigration(vec![
         "settings.nvidia-container-runtime",
         "services.nvidia-container-toolkit",
         "configuration-files.nvidia-container-toolkit",
-    ]))
+    ])
+}
+
+fn run() -> Result<()> {
+    migrate(migration())
 }
 
 // Returning a Result from main makes it print a Debug representation of the error, but with Snafu
@@ -20,3 +24,21 @@ fn main() {
         process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::migration;
+    use migration_helpers::round_trip::{assert_round_trip, fixture};
+
+    #[test]
+    fn round_trip() {
+        // Data from the prior version doesn't have the new settings.
+        assert_round_trip(
+            &mut migration(),
+            &[
+                fixture(&[("settings.motd", "hi".into())], &[]),
+                fixture(&[], &[]),
+            ],
+        );
+    }
+}
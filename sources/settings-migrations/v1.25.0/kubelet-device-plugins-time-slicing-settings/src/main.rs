@@ -1,15 +1,11 @@
-use migration_helpers::common_migrations::AddSettingsMigration;
-use migration_helpers::{migrate, Result};
+use migration_helpers::{migrate_declarative, Result};
 use std::process;
 
-/// We added new settings for configuring the NVIDIA k8s device plugin.
+/// The settings added in this release are listed in migration.toml.
+const MIGRATION: &str = include_str!("../migration.toml");
+
 fn run() -> Result<()> {
-    migrate(AddSettingsMigration(&[
-        "settings.kubelet-device-plugins.nvidia.device-sharing-strategy",
-        "settings.kubelet-device-plugins.nvidia.time-slicing.replicas",
-        "settings.kubelet-device-plugins.nvidia.time-slicing.rename-by-default",
-        "settings.kubelet-device-plugins.nvidia.time-slicing.fail-requests-greater-than-one",
-    ]))
+    migrate_declarative(MIGRATION)
 }
 
 // Returning a Result from main makes it print a Debug representation of the error, but with Snafu
@@ -21,3 +17,42 @@ fn main() {
         process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::MIGRATION;
+    use migration_helpers::declarative::DeclarativeMigration;
+    use migration_helpers::round_trip::{assert_round_trip, fixture};
+    use migration_helpers::Migration;
+
+    fn migration() -> DeclarativeMigration {
+        MIGRATION.parse().unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        // Data from the prior version doesn't have the new settings.
+        assert_round_trip(
+            &mut migration(),
+            &[
+                fixture(
+                    &[(
+                        "settings.kubelet-device-plugins.nvidia.pass-device-specs",
+                        true.into(),
+                    )],
+                    &[],
+                ),
+                fixture(&[], &[]),
+            ],
+        );
+    }
+
+    #[test]
+    fn backward_removes_new_settings() {
+        let setting = "settings.kubelet-device-plugins.nvidia.time-slicing.replicas";
+        let result = migration()
+            .backward(fixture(&[(setting, 2.into())], &[]))
+            .unwrap();
+        assert!(result.data.is_empty());
+    }
+}
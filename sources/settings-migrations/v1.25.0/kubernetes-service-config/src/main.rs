@@ -6,12 +6,16 @@ const OLD_MODE: &str = "0600";
 const NEW_MODE: &str = "0644";
 
 /// We changed the version of configuration mode
-fn run() -> Result<()> {
-    migrate(ReplaceStringMigration {
+fn migration() -> ReplaceStringMigration {
+    ReplaceStringMigration {
         setting: "configuration-files.kubelet-exec-start-conf.mode",
         old_val: OLD_MODE,
         new_val: NEW_MODE,
-    })
+    }
+}
+
+fn run() -> Result<()> {
+    migrate(migration())
 }
 
 // Returning a Result from main makes it print a Debug representation of the error, but with Snafu
@@ -23,3 +27,33 @@ fn main() {
         process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{migration, OLD_MODE};
+    use migration_helpers::round_trip::{assert_round_trip, fixture};
+
+    #[test]
+    fn round_trip() {
+        assert_round_trip(
+            &mut migration(),
+            &[
+                fixture(
+                    &[(
+                        "configuration-files.kubelet-exec-start-conf.mode",
+                        OLD_MODE.into(),
+                    )],
+                    &[],
+                ),
+                fixture(
+                    &[(
+                        "configuration-files.kubelet-exec-start-conf.mode",
+                        "custom".into(),
+                    )],
+                    &[],
+                ),
+                fixture(&[], &[]),
+            ],
+        );
+    }
+}